@@ -1,22 +1,111 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Clone)]
+/// How the user was verified for a punch.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum VerifyMode {
+    Password,
+    Fingerprint,
+    Card,
+    Face,
+    Palm,
+    Unknown(u8),
+}
+
+impl From<u8> for VerifyMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => VerifyMode::Password,
+            1 => VerifyMode::Fingerprint,
+            2 => VerifyMode::Card,
+            15 => VerifyMode::Face,
+            25 => VerifyMode::Palm,
+            other => VerifyMode::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for VerifyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyMode::Password => write!(f, "Password"),
+            VerifyMode::Fingerprint => write!(f, "Fingerprint"),
+            VerifyMode::Card => write!(f, "Card"),
+            VerifyMode::Face => write!(f, "Face"),
+            VerifyMode::Palm => write!(f, "Palm"),
+            VerifyMode::Unknown(value) => write!(f, "Unknown({})", value),
+        }
+    }
+}
+
+/// The punch state selected on the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Punch {
+    CheckIn,
+    CheckOut,
+    BreakOut,
+    BreakIn,
+    OvertimeIn,
+    OvertimeOut,
+    Unknown(u8),
+}
+
+impl From<u8> for Punch {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Punch::CheckIn,
+            1 => Punch::CheckOut,
+            2 => Punch::BreakOut,
+            3 => Punch::BreakIn,
+            4 => Punch::OvertimeIn,
+            5 => Punch::OvertimeOut,
+            other => Punch::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for Punch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Punch::CheckIn => write!(f, "Check-In"),
+            Punch::CheckOut => write!(f, "Check-Out"),
+            Punch::BreakOut => write!(f, "Break-Out"),
+            Punch::BreakIn => write!(f, "Break-In"),
+            Punch::OvertimeIn => write!(f, "OT-In"),
+            Punch::OvertimeOut => write!(f, "OT-Out"),
+            Punch::Unknown(value) => write!(f, "Unknown({})", value),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Attendance {
     pub uid: u32,
     pub user_id: u32,
-    pub timestamp: String,
-    pub status: String,
-    pub punch: i32,
+    /// Device-local time of the punch.
+    pub timestamp: NaiveDateTime,
+    pub status: VerifyMode,
+    pub punch: Punch,
+    pub workcode: u32,
 }
 
 impl Attendance {
-    pub fn new(user_id: u32, timestamp: String, status: String, punch: i32, uid: u32) -> Self {
+    pub fn new(
+        user_id: u32,
+        timestamp: NaiveDateTime,
+        status: VerifyMode,
+        punch: Punch,
+        uid: u32,
+        workcode: u32,
+    ) -> Self {
         Self {
             uid,
             user_id,
             timestamp,
             status,
             punch,
+            workcode,
         }
     }
 }
@@ -65,15 +65,28 @@
 //         return attendances
 
 use std::{
-    io::{Read, Write},
+    collections::VecDeque,
+    io::{ErrorKind, Read, Write},
     net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
-    time::Duration,
+    ops::{Deref, DerefMut},
+    time::{Duration, Instant},
 };
 
 use byteorder::{ByteOrder, LittleEndian};
-use chrono::{DateTime, FixedOffset, Local, NaiveDateTime};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeDelta, Timelike, Utc,
+};
 
-use crate::{attandance::Attendance, consts, exception::ZKError, user::User};
+use crate::{
+    attandance::Attendance,
+    consts,
+    device::{ClearReport, DeviceInfo, DeviceSizes, DoorState, VoicePrompt},
+    event::{EnrollStep, LiveEvent},
+    exception::ZKError,
+    finger::Finger,
+    oplog::OpLogEntry,
+    user::User,
+};
 
 #[derive(Debug)]
 pub struct ZK {
@@ -84,12 +97,21 @@ pub struct ZK {
     pub reply_id: u16,
     pub is_connect: bool,
     pub user_packet_size: usize,
+    pub user_packet_size_detected: bool,
     pub users: usize,
+    pub fingers: usize,
     pub records: usize,
     pub verbose: bool,
     pub data: Vec<u8>,
     pub response: u16,
+    pub header: [u16; 4],
     pub force_udp: bool,
+    pub is_enabled: bool,
+    pub password: u32,
+    pub timezone: Option<FixedOffset>,
+    /// Uid and raw user_id of the records the last [`ZK::get_users`] call
+    /// skipped because their user_id was not numeric.
+    pub skipped_users: Vec<(u16, String)>,
 }
 
 #[derive(Debug)]
@@ -99,17 +121,282 @@ pub enum ZkSocket {
 }
 
 impl ZkSocket {
+    /// Sends one protocol packet. Over TCP the packet is wrapped in the 8-byte
+    /// envelope (two magic words plus the packet length).
     pub fn send_to(&mut self, buf: &[u8], target: SocketAddr) -> std::io::Result<usize> {
         match self {
             ZkSocket::Udp(sock) => sock.send_to(buf, target),
-            ZkSocket::Tcp(sock) => sock.write(buf),
+            ZkSocket::Tcp(sock) => {
+                let mut packet = Vec::with_capacity(8 + buf.len());
+                packet.extend(&consts::MACHINE_PREPARE_DATA_1.to_le_bytes());
+                packet.extend(&consts::MACHINE_PREPARE_DATA_2.to_le_bytes());
+                packet.extend(&(buf.len() as u32).to_le_bytes());
+                packet.extend(buf);
+                sock.write_all(&packet)?;
+                Ok(buf.len())
+            }
+        }
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            ZkSocket::Udp(sock) => sock.set_read_timeout(timeout),
+            ZkSocket::Tcp(sock) => sock.set_read_timeout(timeout),
         }
     }
 
-    pub fn recv(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    /// Receives one protocol packet, header included. UDP datagrams are read
+    /// into a buffer of `size` bytes; TCP frames are read by their declared
+    /// length regardless of how they were split across reads. A read timeout
+    /// is only returned when no byte of the frame has arrived yet, so the
+    /// stream never loses its place.
+    pub fn recv_packet(&mut self, size: usize) -> std::io::Result<Vec<u8>> {
         match self {
-            ZkSocket::Udp(sock) => sock.recv(buf),
-            ZkSocket::Tcp(sock) => sock.read(buf),
+            ZkSocket::Udp(sock) => {
+                let mut buf = vec![0u8; size];
+                let len = sock.recv(&mut buf)?;
+                buf.truncate(len);
+                Ok(buf)
+            }
+            ZkSocket::Tcp(sock) => {
+                let mut top = [0u8; 8];
+                read_frame_bytes(sock, &mut top, false)?;
+                if LittleEndian::read_u16(&top[0..2]) != consts::MACHINE_PREPARE_DATA_1
+                    || LittleEndian::read_u16(&top[2..4]) != consts::MACHINE_PREPARE_DATA_2
+                {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "invalid TCP frame header",
+                    ));
+                }
+
+                let len = LittleEndian::read_u32(&top[4..8]) as usize;
+                if len > MAX_TCP_FRAME {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "TCP frame length exceeds limit",
+                    ));
+                }
+                let mut buf = vec![0u8; len];
+                read_frame_bytes(sock, &mut buf, true)?;
+                Ok(buf)
+            }
+        }
+    }
+}
+
+/// Size of one `CMD_READ_BUFFER` request in [`ZK::read_with_buffer`].
+const READ_CHUNK: usize = 16 * 1024;
+
+/// Largest TCP frame accepted: a full read chunk, its packet header and some
+/// slack. Anything longer is treated as a corrupt stream rather than allocated.
+const MAX_TCP_FRAME: usize = READ_CHUNK + 8 + 1024;
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// Maps a failed receive: a read timeout becomes [`ZKError::Timeout`] and a
+/// connection closed or reset by the device [`ZKError::ConnectionError`].
+fn recv_error(e: std::io::Error) -> ZKError {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => ZKError::Timeout,
+        ErrorKind::UnexpectedEof
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::BrokenPipe => ZKError::ConnectionError,
+        _ => ZKError::NetworkError,
+    }
+}
+
+/// Fills `buf` from a TCP stream. Timeouts are passed through only while
+/// nothing of the frame has been read; once a frame has started, a few
+/// timeouts are waited out and a frame that stalls beyond that is an error.
+fn read_frame_bytes(sock: &mut TcpStream, buf: &mut [u8], started: bool) -> std::io::Result<()> {
+    const MAX_STALLS: usize = 3;
+
+    let mut read = 0;
+    let mut stalls = 0;
+    while read < buf.len() {
+        match sock.read(&mut buf[read..]) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) if is_timeout(&e) && (started || read > 0) => {
+                stalls += 1;
+                if stalls > MAX_STALLS {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        "TCP frame stalled",
+                    ));
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Guard returned by [`ZK::disabled`]; re-enables the device on drop.
+pub struct DisabledDevice<'a> {
+    zk: &'a mut ZK,
+    reenable: bool,
+}
+
+impl Deref for DisabledDevice<'_> {
+    type Target = ZK;
+
+    fn deref(&self) -> &ZK {
+        self.zk
+    }
+}
+
+impl DerefMut for DisabledDevice<'_> {
+    fn deref_mut(&mut self) -> &mut ZK {
+        self.zk
+    }
+}
+
+impl Drop for DisabledDevice<'_> {
+    fn drop(&mut self) {
+        if self.reenable {
+            let _ = self.zk.enable_device();
+        }
+    }
+}
+
+/// Iterator returned by [`ZK::live_capture`].
+pub struct LiveCapture<'a> {
+    zk: &'a mut ZK,
+    users: Vec<User>,
+    was_enabled: bool,
+    pending: VecDeque<LiveEvent>,
+    done: bool,
+}
+
+impl LiveCapture<'_> {
+    fn decode_attendances(&mut self, mut data: &[u8]) -> Result<(), ZKError> {
+        while data.len() >= 10 {
+            // Only the 52 byte layout packs several records into one event
+            let (record, next) = data.split_at(if data.len() >= 52 { 52 } else { data.len() });
+
+            let (user_id, rest) = match record.len() {
+                10 | 14 => (LittleEndian::read_u16(&record[0..2]).into(), &record[2..]),
+                12 => (LittleEndian::read_u32(&record[0..4]), &record[4..]),
+                len if len >= 32 => match decode_str(&record[0..24]).parse() {
+                    Ok(user_id) => (user_id, &record[24..]),
+                    // Not a user this crate can represent; pass the raw record on
+                    Err(_) => {
+                        self.pending.push_back(LiveEvent::Unknown {
+                            event: consts::EF_ATTLOG as u16,
+                            data: record.to_vec(),
+                        });
+                        data = next;
+                        continue;
+                    }
+                },
+                _ => break,
+            };
+            let status = rest[0];
+            let punch = rest[1];
+            let timestamp = decode_timehex(&rest[2..8])?;
+
+            let uid = self
+                .users
+                .iter()
+                .find(|u| u.user_id == user_id)
+                .map_or(user_id, |u| u.uid.into());
+
+            self.pending
+                .push_back(LiveEvent::Attendance(Attendance::new(
+                    user_id,
+                    timestamp,
+                    status.into(),
+                    punch.into(),
+                    uid,
+                    0,
+                )));
+
+            data = next;
+        }
+
+        Ok(())
+    }
+
+    fn decode_event(&mut self, event: u16, data: &[u8]) -> Result<(), ZKError> {
+        let word = |i: usize| data.get(i..i + 2).map_or(0, LittleEndian::read_u16);
+
+        let live_event = match event as u32 {
+            consts::EF_ATTLOG => return self.decode_attendances(data),
+            consts::EF_FINGER => LiveEvent::Finger,
+            consts::EF_ENROLLUSER => LiveEvent::EnrollUser { uid: word(0) },
+            consts::EF_ENROLLFINGER => LiveEvent::EnrollFinger {
+                result: word(0),
+                size: word(2),
+            },
+            consts::EF_BUTTON => LiveEvent::Button { key: word(0) },
+            consts::EF_UNLOCK => LiveEvent::Unlock,
+            consts::EF_VERIFY => LiveEvent::Verify { uid: word(0) },
+            consts::EF_ALARM => LiveEvent::Alarm { code: word(0) },
+            _ => LiveEvent::Unknown {
+                event,
+                data: data.to_vec(),
+            },
+        };
+        self.pending.push_back(live_event);
+        Ok(())
+    }
+}
+
+impl Iterator for LiveCapture<'_> {
+    type Item = Result<LiveEvent, ZKError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.done {
+                return None;
+            }
+
+            let packet = match self.zk.socket.recv_packet(1032) {
+                Ok(packet) => packet,
+                Err(e) if is_timeout(&e) => {
+                    return Some(Ok(LiveEvent::Heartbeat));
+                }
+                Err(_e) => {
+                    self.done = true;
+                    return Some(Err(ZKError::NetworkError));
+                }
+            };
+            if packet.len() < 8 {
+                continue;
+            }
+
+            if let Err(e) = self.zk.ack_ok() {
+                self.done = true;
+                return Some(Err(e));
+            }
+
+            if LittleEndian::read_u16(&packet[0..2]) != consts::CMD_REG_EVENT {
+                continue;
+            }
+
+            // The event flag travels in the session id field of the header
+            let event = LittleEndian::read_u16(&packet[4..6]);
+            if let Err(e) = self.decode_event(event, &packet[8..]) {
+                return Some(Err(e));
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl Drop for LiveCapture<'_> {
+    fn drop(&mut self) {
+        let _ = self.zk.reg_event(0);
+        if !self.was_enabled {
+            let _ = self.zk.disable_device();
         }
     }
 }
@@ -118,7 +405,9 @@ struct CommandResponse {
     status: bool,
 }
 
-fn decode_time(raw: &[u8]) -> DateTime<Local> {
+/// Decodes the packed device timestamp. The result is in the device's own
+/// local time; see [`ZK::to_utc`].
+fn decode_time(raw: &[u8]) -> Result<NaiveDateTime, ZKError> {
     let mut t = LittleEndian::read_u32(raw);
     let second = t % 60;
     t /= 60;
@@ -132,15 +421,231 @@ fn decode_time(raw: &[u8]) -> DateTime<Local> {
     t /= 12;
     let year = t + 2000;
 
-    let naive = NaiveDateTime::parse_from_str(
-        &format!(
-            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-            year, month, day, hour, minute, second
-        ),
-        "%Y-%m-%d %H:%M:%S",
-    )
-    .unwrap();
-    DateTime::<Local>::from_naive_utc_and_offset(naive, FixedOffset::east_opt(0).unwrap())
+    NaiveDate::from_ymd_opt(year as i32, month, day)
+        .and_then(|date| date.and_hms_opt(hour, minute, second))
+        .ok_or(ZKError::InvalidTime)
+}
+
+/// Decodes the six byte `year, month, day, hour, minute, second` timestamp
+/// used in live events.
+fn decode_timehex(raw: &[u8]) -> Result<NaiveDateTime, ZKError> {
+    NaiveDate::from_ymd_opt(raw[0] as i32 + 2000, raw[1].into(), raw[2].into())
+        .and_then(|date| date.and_hms_opt(raw[3].into(), raw[4].into(), raw[5].into()))
+        .ok_or(ZKError::InvalidTime)
+}
+
+/// Packs a timestamp the way [`decode_time`] reads it. The format only holds
+/// two-digit years, so anything outside 2000..=2099 is rejected.
+fn encode_time(t: &NaiveDateTime) -> Result<u32, ZKError> {
+    if !(2000..=2099).contains(&t.year()) {
+        return Err(ZKError::InvalidTime);
+    }
+
+    let days = (t.year() as u32 - 2000) * 12 * 31 + (t.month0() * 31) + t.day0();
+    Ok(days * (24 * 60 * 60) + (t.hour() * 60 + t.minute()) * 60 + t.second())
+}
+
+/// Scrambles the comm key the way the device expects it in `CMD_AUTH`.
+fn make_commkey(key: u32, session_id: u16, ticks: u8) -> [u8; 4] {
+    let mut k = key.reverse_bits();
+    k = k.wrapping_add(session_id as u32);
+
+    let mut b = k.to_le_bytes();
+    b[0] ^= b'Z';
+    b[1] ^= b'K';
+    b[2] ^= b'S';
+    b[3] ^= b'O';
+
+    // swap the two u16 halves
+    let b = [b[2], b[3], b[0], b[1]];
+
+    [b[0] ^ ticks, b[1] ^ ticks, ticks, b[3] ^ ticks]
+}
+
+/// Decodes a fixed-width, NUL padded string field.
+fn decode_str(raw: &[u8]) -> String {
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    String::from_utf8_lossy(&raw[..end]).trim().to_string()
+}
+
+/// Decodes an attendance log as returned for `CMD_ATTLOG_RRQ`: a u32 total
+/// size followed by `records` records of 8, 16 or 40 bytes. A trailing partial
+/// record is ignored. `users` maps between uid and user_id where the record
+/// only carries one of them.
+fn decode_attendance_records(
+    data: &[u8],
+    records: usize,
+    users: &[User],
+) -> Result<Vec<Attendance>, ZKError> {
+    if data.len() < 4 || records == 0 {
+        return Ok(vec![]);
+    }
+
+    let total_size = LittleEndian::read_u32(&data[..4]) as usize;
+    let record_size = total_size / records;
+    if !matches!(record_size, 8 | 16 | 40) {
+        return Err(ZKError::UnsupportedRecordSize);
+    }
+
+    let mut attendances = Vec::new();
+    for chunk in data[4..].chunks_exact(record_size) {
+        let attendance = match record_size {
+            8 => {
+                let uid = LittleEndian::read_u16(&chunk[0..2]);
+                let status = chunk[2];
+                let timestamp = decode_time(&chunk[3..7])?;
+                let punch = chunk[7];
+
+                let user_id = users
+                    .iter()
+                    .find(|u| u.uid == uid)
+                    .map_or(uid.into(), |u| u.user_id);
+
+                Attendance::new(
+                    user_id,
+                    timestamp,
+                    status.into(),
+                    punch.into(),
+                    uid.into(),
+                    0,
+                )
+            }
+            16 => {
+                let mut user_id = LittleEndian::read_u32(&chunk[0..4]);
+                let timestamp = decode_time(&chunk[4..8])?;
+                let status = chunk[8];
+                let punch = chunk[9];
+                let workcode = LittleEndian::read_u32(&chunk[12..16]);
+
+                // The record carries the user_id, but older firmwares put the uid there.
+                let uid = match users.iter().find(|u| u.user_id == user_id) {
+                    Some(user) => user.uid.into(),
+                    None => match users.iter().find(|u| u32::from(u.uid) == user_id) {
+                        Some(user) => {
+                            user_id = user.user_id;
+                            user.uid.into()
+                        }
+                        None => user_id,
+                    },
+                };
+
+                Attendance::new(
+                    user_id,
+                    timestamp,
+                    status.into(),
+                    punch.into(),
+                    uid,
+                    workcode,
+                )
+            }
+            _ => {
+                let uid = LittleEndian::read_u16(&chunk[0..2]);
+                let status = chunk[26];
+                let timestamp = decode_time(&chunk[27..31])?;
+                let punch = chunk[31];
+
+                let user_id = decode_str(&chunk[2..26])
+                    .parse::<u32>()
+                    .ok()
+                    .or_else(|| users.iter().find(|u| u.uid == uid).map(|u| u.user_id))
+                    .unwrap_or(uid.into());
+
+                Attendance::new(
+                    user_id,
+                    timestamp,
+                    status.into(),
+                    punch.into(),
+                    uid.into(),
+                    0,
+                )
+            }
+        };
+        attendances.push(attendance);
+    }
+
+    Ok(attendances)
+}
+
+/// Decodes a template dump as returned for `FCT_FINGERTMP`: a u32 total size
+/// followed by variable length `size, uid, fid, valid, template` records.
+/// Decoding stops at the first record that does not fit.
+fn decode_template_records(data: &[u8]) -> Vec<Finger> {
+    if data.len() < 4 {
+        return vec![];
+    }
+
+    let total_size = (LittleEndian::read_u32(&data[..4]) as usize).min(data.len() - 4);
+    let data = &data[4..4 + total_size];
+
+    let mut templates = Vec::new();
+    let mut i = 0;
+    while i + 6 <= data.len() {
+        let size = LittleEndian::read_u16(&data[i..i + 2]) as usize;
+        if size < 6 || i + size > data.len() {
+            break;
+        }
+
+        let uid = LittleEndian::read_u16(&data[i + 2..i + 4]);
+        let fid = data[i + 4];
+        let valid = data[i + 5];
+        templates.push(Finger::new(uid, fid, valid, data[i + 6..i + size].to_vec()));
+
+        i += size;
+    }
+
+    templates
+}
+
+/// Decodes an operation log as returned for `CMD_OPLOG_RRQ`: a u32 total size
+/// followed by 16-byte records. A declared size that is not a whole number of
+/// records, or that exceeds the data received, is an error.
+fn decode_oplog_records(data: &[u8]) -> Result<Vec<OpLogEntry>, ZKError> {
+    const RECORD_SIZE: usize = 16;
+
+    if data.len() < 4 {
+        return Ok(vec![]);
+    }
+
+    let total_size = LittleEndian::read_u32(&data[..4]) as usize;
+    if !total_size.is_multiple_of(RECORD_SIZE) {
+        return Err(ZKError::UnsupportedRecordSize);
+    }
+    if total_size > data.len() - 4 {
+        return Err(ZKError::ResponseError);
+    }
+
+    // admin id (2), operation (1), reserved (1), time (4), params (4 x 2)
+    let mut entries = Vec::new();
+    for chunk in data[4..4 + total_size].chunks_exact(RECORD_SIZE) {
+        let admin_id = LittleEndian::read_u16(&chunk[0..2]);
+        let operation = chunk[2];
+        let timestamp = decode_time(&chunk[4..8])?;
+        let mut params = [0u16; 4];
+        for (i, param) in params.iter_mut().enumerate() {
+            *param = LittleEndian::read_u16(&chunk[8 + i * 2..10 + i * 2]);
+        }
+
+        entries.push(OpLogEntry::new(
+            admin_id,
+            operation.into(),
+            timestamp,
+            params,
+        ));
+    }
+
+    Ok(entries)
+}
+
+/// Extracts the value from a `key=value\0` option reply. Replies for another
+/// key or without a value separator yield `None`.
+fn parse_option_reply(raw: &[u8], key: &str) -> Option<String> {
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    let reply = String::from_utf8_lossy(&raw[..end]);
+
+    reply
+        .strip_prefix(key)
+        .and_then(|rest| rest.strip_prefix('='))
+        .map(str::to_string)
 }
 
 fn create_checksum(buf: &[u8]) -> u16 {
@@ -164,7 +669,7 @@ fn create_checksum(buf: &[u8]) -> u16 {
         checksum -= 0xFFFF;
     }
 
-    (!checksum as u16) & 0xFFFF
+    !checksum as u16
 }
 
 impl ZK {
@@ -173,13 +678,14 @@ impl ZK {
         timeout_secs: u64,
         verbose: bool,
         force_udp: bool,
+        password: Option<u32>,
     ) -> Result<Self, ZKError> {
         let timeout = Duration::from_secs(timeout_secs);
         let address = addr
             .to_socket_addrs()
             .unwrap()
             .next()
-            .ok_or_else(|| ZKError::ResponseError)?;
+            .ok_or(ZKError::ResponseError)?;
 
         let socket = if force_udp {
             let udp = UdpSocket::bind("0.0.0.0:0").map_err(|_e| ZKError::NetworkError)?;
@@ -205,60 +711,461 @@ impl ZK {
             reply_id: 0xffff - 1,
             is_connect: false,
             user_packet_size: 28,
+            user_packet_size_detected: false,
             users: 0,
+            fingers: 0,
             records: 0,
             verbose,
             data: Vec::new(),
             response: 0,
+            header: [0; 4],
             force_udp,
+            is_enabled: true,
+            password: password.unwrap_or(0),
+            timezone: None,
+            skipped_users: Vec::new(),
         })
     }
 
-    pub fn get_attendance(&mut self) -> Result<Vec<Attendance>, ZKError> {
-        self.read_sizes()?;
-        if self.records == 0 {
-            return Ok(vec![]);
+    /// Opens a session with the device (`CMD_CONNECT`) and keeps the session id
+    /// it hands back, which every following command has to carry.
+    pub fn connect(&mut self) -> Result<(), ZKError> {
+        self.session_id = 0;
+        self.reply_id = 0xffff - 1;
+
+        let mut response = self.send_command(consts::CMD_CONNECT, &[], 1024)?;
+        self.session_id = self.header[2];
+
+        if self.response == consts::CMD_ACK_UNAUTH {
+            let command_string = make_commkey(self.password, self.session_id, 50);
+            response = self.send_command(consts::CMD_AUTH, &command_string, 1024)?;
+            if self.response == consts::CMD_ACK_UNAUTH {
+                return Err(ZKError::Unauthorized);
+            }
+        }
+
+        if !response.status {
+            return Err(ZKError::ConnectionError);
+        }
+
+        self.is_connect = true;
+        Ok(())
+    }
+
+    /// Closes the current session (`CMD_EXIT`).
+    pub fn disconnect(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_EXIT, &[], 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.is_connect = false;
+        self.session_id = 0;
+        Ok(())
+    }
+
+    /// Restarts the device. The session ends with it, so `ZK` is left
+    /// disconnected.
+    pub fn restart(&mut self) -> Result<(), ZKError> {
+        self.send_terminating_command(consts::CMD_RESTART)
+    }
+
+    /// Shuts the device down. The session ends with it, so `ZK` is left
+    /// disconnected.
+    pub fn poweroff(&mut self) -> Result<(), ZKError> {
+        self.send_terminating_command(consts::CMD_POWEROFF)
+    }
+
+    pub fn sleep(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_SLEEP, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    pub fn resume(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_RESUME, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    fn send_terminating_command(&mut self, command: u16) -> Result<(), ZKError> {
+        let buf = self.create_header(command, &[])?;
+        self.socket
+            .send_to(&buf, self.address)
+            .map_err(|_e| ZKError::NetworkError)?;
+
+        match self.receive_reply(8) {
+            Ok(_) if self.response == consts::CMD_ACK_OK => {}
+            Ok(_) => return Err(ZKError::ResponseError),
+            // The device may go down before its reply reaches us
+            Err(ZKError::Timeout | ZKError::ConnectionError) => {}
+            Err(e) => return Err(e),
+        }
+
+        self.is_connect = false;
+        self.session_id = 0;
+        Ok(())
+    }
+
+    pub fn enable_device(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_ENABLEDEVICE, &[], 8)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.is_enabled = true;
+        Ok(())
+    }
+
+    /// Locks the terminal's keypad and sensor, typically showing
+    /// "working..." on its display.
+    pub fn disable_device(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_DISABLEDEVICE, &[], 8)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.is_enabled = false;
+        Ok(())
+    }
+
+    /// Disables the device until the returned guard is dropped. If the device
+    /// was already disabled, it is left disabled afterwards.
+    pub fn disabled(&mut self) -> Result<DisabledDevice<'_>, ZKError> {
+        let reenable = self.is_enabled;
+        if reenable {
+            self.disable_device()?;
         }
 
+        Ok(DisabledDevice { zk: self, reenable })
+    }
+
+    /// Releases the door lock relay for `duration` (in steps of 100ms).
+    pub fn unlock(&mut self, duration: Duration) -> Result<(), ZKError> {
+        let command_string = ((duration.as_millis() / 100) as u32).to_le_bytes();
+        self.send_command(consts::CMD_UNLOCK, &command_string, 8)?;
+        match self.response {
+            consts::CMD_ACK_OK => Ok(()),
+            consts::CMD_ACK_UNKNOWN | consts::CMD_ACK_ERROR_CMD => Err(ZKError::Unsupported),
+            _ => Err(ZKError::ResponseError),
+        }
+    }
+
+    pub fn get_door_state(&mut self) -> Result<DoorState, ZKError> {
+        self.send_command(consts::CMD_DOORSTATE_RRQ, &[], 8)?;
+        match self.response {
+            consts::CMD_ACK_OK => Ok(DoorState::Open),
+            consts::CMD_ACK_ERROR => Ok(DoorState::Closed),
+            consts::CMD_ACK_UNKNOWN | consts::CMD_ACK_ERROR_CMD => Err(ZKError::Unsupported),
+            _ => Err(ZKError::ResponseError),
+        }
+    }
+
+    /// Shows `text` on line `line` of the device's LCD.
+    pub fn write_lcd(&mut self, line: u16, text: &str) -> Result<(), ZKError> {
+        let mut command_string = line.to_le_bytes().to_vec();
+        command_string.push(0);
+        command_string.push(b' ');
+        command_string.extend(text.as_bytes());
+
+        let response = self.send_command(consts::CMD_WRITE_LCD, &command_string, 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    pub fn clear_lcd(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_CLEAR_LCD, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    pub fn test_voice(&mut self, prompt: VoicePrompt) -> Result<(), ZKError> {
+        let command_string = prompt.index().to_le_bytes();
+        let response = self.send_command(consts::CMD_TESTVOICE, &command_string, 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    /// Registers for the events in `flags` (the `consts::EF_*` bits); 0
+    /// unregisters.
+    pub fn reg_event(&mut self, flags: u32) -> Result<(), ZKError> {
+        let mut response = self.send_command(consts::CMD_REG_EVENT, &flags.to_le_bytes(), 1024)?;
+        // Events already queued by the device may arrive before our reply
+        while self.response == consts::CMD_REG_EVENT {
+            self.ack_ok()?;
+            response = self.receive_reply(1024)?;
+        }
+
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    /// Streams the events in `flags` (the `consts::EF_*` bits) as the device
+    /// pushes them. A read timeout yields [`LiveEvent::Heartbeat`]; dropping
+    /// the returned iterator unregisters from the events.
+    pub fn live_capture(&mut self, flags: u32) -> Result<LiveCapture<'_>, ZKError> {
         let users = self.get_users()?;
-        let (mut attendance_data, size) = self.read_with_buffer(consts::CMD_ATTLOG_RRQ, 0, 0)?;
+        let was_enabled = self.is_enabled;
+        if !was_enabled {
+            self.enable_device()?;
+        }
+        self.reg_event(flags)?;
+
+        Ok(LiveCapture {
+            zk: self,
+            users,
+            was_enabled,
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
 
-        if size < 4 {
-            return Ok(vec![]);
+    /// Aborts a pending capture or enrollment on the device.
+    pub fn cancel_capture(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_CANCELCAPTURE, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
         }
+    }
 
-        let total_size = LittleEndian::read_u32(&attendance_data[..4]) as usize;
-        let record_size = total_size / self.records;
-        attendance_data = attendance_data[4..].to_vec();
+    /// Puts the device back into its normal verification mode.
+    pub fn verify_user(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_STARTVERIFY, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
 
-        let mut attendances = Vec::new();
+    /// Enrolls finger `fid` of `uid` from the device's sensor and returns the
+    /// new template. `on_step` is called before and after each of the three
+    /// presses, and about once a second while waiting for one; returning
+    /// `false` cancels the enrollment. Fails with [`ZKError::Timeout`] when the
+    /// enrollment has not finished within `timeout`.
+    pub fn enroll_user<F>(
+        &mut self,
+        uid: u16,
+        fid: u8,
+        timeout: Duration,
+        mut on_step: F,
+    ) -> Result<Finger, ZKError>
+    where
+        F: FnMut(EnrollStep) -> bool,
+    {
+        const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+        let user_id = self
+            .get_users()?
+            .into_iter()
+            .find(|u| u.uid == uid)
+            .ok_or(ZKError::UserNotFound)?
+            .user_id;
+
+        let command_string = if matches!(self.socket, ZkSocket::Tcp(_)) {
+            let mut command_string = vec![0u8; 26];
+            let user_id = user_id.to_string();
+            let len = user_id.len().min(24);
+            command_string[..len].copy_from_slice(&user_id.as_bytes()[..len]);
+            command_string[24] = fid;
+            command_string[25] = 1;
+            command_string
+        } else {
+            let mut command_string = user_id.to_le_bytes().to_vec();
+            command_string.push(fid);
+            command_string
+        };
 
-        let mut i = 0;
-        while i + record_size <= attendance_data.len() {
-            let chunk = &attendance_data[i..i + record_size];
-            if record_size == 8 {
-                let uid = LittleEndian::read_u16(&chunk[0..2]);
-                let status = chunk[2];
-                let timestamp = decode_time(&chunk[3..7]);
-                let punch: i32 = chunk[7].into();
+        self.cancel_capture()?;
+        let response = self.send_command(consts::CMD_STARTENROLL, &command_string, 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.socket
+            .set_read_timeout(Some(
+                POLL_INTERVAL.min(timeout).max(Duration::from_millis(1)),
+            ))
+            .map_err(|_e| ZKError::NetworkError)?;
+        let deadline = Instant::now() + timeout;
+        let result = self.follow_enrollment(deadline, &mut on_step);
+        let _ = self.socket.set_read_timeout(Some(self.timeout));
+
+        if result.is_err() {
+            let _ = self.cancel_capture();
+        }
+        let _ = self.reg_event(0);
+        let _ = self.verify_user();
+        result?;
+
+        self.get_user_template(uid, fid)?
+            .ok_or(ZKError::EnrollFailed)
+    }
+
+    fn follow_enrollment<F>(&mut self, deadline: Instant, on_step: &mut F) -> Result<(), ZKError>
+    where
+        F: FnMut(EnrollStep) -> bool,
+    {
+        for press in 1..=3 {
+            if !on_step(EnrollStep::Place(press)) {
+                return Err(ZKError::Cancelled);
+            }
+
+            // 0 here means the enrollment was rejected, 4 and 6 a timeout
+            let result = self.receive_enroll_event(press, deadline, on_step)?;
+            if matches!(result, 0 | 4 | 6) {
+                return Err(ZKError::EnrollFailed);
+            }
+
+            // 0x64 acknowledges the press
+            let result = self.receive_enroll_event(press, deadline, on_step)?;
+            if result != 0x64 {
+                return Err(ZKError::EnrollFailed);
+            }
+
+            if !on_step(EnrollStep::Captured(press)) {
+                return Err(ZKError::Cancelled);
+            }
+        }
+
+        // The final event is 0 on success, 5 for an already enrolled finger
+        let result = self.receive_enroll_event(3, deadline, on_step)?;
+        if result & 0xff != 0 {
+            return Err(ZKError::EnrollFailed);
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the next enrollment event and returns its result code. Each
+    /// read timeout is reported as [`EnrollStep::Waiting`] so the caller can
+    /// cancel.
+    fn receive_enroll_event<F>(
+        &mut self,
+        press: u8,
+        deadline: Instant,
+        on_step: &mut F,
+    ) -> Result<u16, ZKError>
+    where
+        F: FnMut(EnrollStep) -> bool,
+    {
+        loop {
+            let packet = match self.socket.recv_packet(1032) {
+                Ok(packet) => packet,
+                Err(e) if is_timeout(&e) => {
+                    if Instant::now() >= deadline {
+                        return Err(ZKError::Timeout);
+                    }
+                    if !on_step(EnrollStep::Waiting(press)) {
+                        return Err(ZKError::Cancelled);
+                    }
+                    continue;
+                }
+                Err(_e) => return Err(ZKError::NetworkError),
+            };
+            if packet.len() < 8 {
+                continue;
+            }
+
+            self.ack_ok()?;
+
+            // Stray replies are acknowledged but are not enrollment progress
+            if LittleEndian::read_u16(&packet[0..2]) != consts::CMD_REG_EVENT {
+                if Instant::now() >= deadline {
+                    return Err(ZKError::Timeout);
+                }
+                continue;
+            }
+
+            return Ok(packet.get(8..10).map_or(0, LittleEndian::read_u16));
+        }
+    }
+
+    /// Converts a device-local timestamp to UTC using the configured
+    /// `timezone`. Returns `None` when no timezone is configured.
+    pub fn to_utc(&self, timestamp: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let timezone = self.timezone?;
+        timestamp
+            .and_local_timezone(timezone)
+            .single()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    pub fn get_time(&mut self) -> Result<NaiveDateTime, ZKError> {
+        let response = self.send_command(consts::CMD_GET_TIME, &[], 1032)?;
+        if !response.status || self.data.len() < 4 {
+            return Err(ZKError::ResponseError);
+        }
+
+        decode_time(&self.data[..4])
+    }
+
+    /// Sets the device clock. The device stores two-digit years, so only
+    /// 2000..=2099 is accepted; other years fail with [`ZKError::InvalidTime`].
+    pub fn set_time(&mut self, timestamp: NaiveDateTime) -> Result<(), ZKError> {
+        let command_string = encode_time(&timestamp)?.to_le_bytes();
+        let response = self.send_command(consts::CMD_SET_TIME, &command_string, 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    /// Sets the device clock to the current time in the device's `timezone`
+    /// and returns how far the device was off (positive when it was ahead).
+    /// Without a configured timezone the device is assumed to share the
+    /// host's local time zone.
+    pub fn sync_time_with_host(&mut self) -> Result<TimeDelta, ZKError> {
+        let device_time = self.get_time()?;
+        let host_time = match self.timezone {
+            Some(timezone) => Utc::now().with_timezone(&timezone).naive_local(),
+            None => Local::now().naive_local(),
+        };
+        self.set_time(host_time)?;
+
+        Ok(device_time - host_time.with_nanosecond(0).unwrap_or(host_time))
+    }
+
+    pub fn get_attendance(&mut self) -> Result<Vec<Attendance>, ZKError> {
+        let mut zk = self.disabled()?;
+        zk.read_sizes()?;
+        if zk.records == 0 {
+            return Ok(vec![]);
+        }
 
-                let user_id = users.iter().find(|u| u.uid == uid).unwrap().user_id;
+        let users = zk.get_users()?;
+        let (attendance_data, _) = zk.read_with_buffer(consts::CMD_ATTLOG_RRQ, 0, 0)?;
 
-                attendances.push(Attendance::new(
-                    user_id,
-                    timestamp.to_string(),
-                    status.to_string(),
-                    punch,
-                    uid.into(),
-                ));
-            } else {
-                // Support other record sizes later (16, 40, etc)
-                return Err(ZKError::UnsupportedRecordSize);
-            }
-            i += record_size;
-        }
+        decode_attendance_records(&attendance_data, zk.records, &users)
+    }
+
+    /// Reads the operation (admin) log.
+    pub fn get_operation_log(&mut self) -> Result<Vec<OpLogEntry>, ZKError> {
+        let mut zk = self.disabled()?;
+        let (data, _) = zk.read_with_buffer(consts::CMD_OPLOG_RRQ, consts::FCT_OPLOG, 0)?;
 
-        Ok(attendances)
+        decode_oplog_records(&data)
     }
 
     fn create_header(&mut self, command: u16, command_string: &[u8]) -> Result<Vec<u8>, ZKError> {
@@ -271,7 +1178,7 @@ impl ZK {
 
         let checksum = create_checksum(&buf);
         self.reply_id = self.reply_id.wrapping_add(1);
-        if self.reply_id >= 0xFFFF {
+        if self.reply_id == 0xFFFF {
             self.reply_id = 0;
         }
 
@@ -287,15 +1194,28 @@ impl ZK {
         payload: &[u8],
         response_size: usize,
     ) -> Result<CommandResponse, ZKError> {
-        // TODO: implement create_header like Python version
         let buf = self.create_header(command, payload)?;
 
-        self.socket.send_to(&buf, self.address).unwrap();
-        let mut recv_buf = vec![0u8; response_size];
-        let len = self.socket.recv(&mut recv_buf).unwrap();
+        self.socket
+            .send_to(&buf, self.address)
+            .map_err(|_e| ZKError::NetworkError)?;
+        self.receive_reply(response_size)
+    }
+
+    fn receive_reply(&mut self, response_size: usize) -> Result<CommandResponse, ZKError> {
+        let recv_buf = self
+            .socket
+            .recv_packet(response_size.max(8))
+            .map_err(recv_error)?;
+        if recv_buf.len() < 8 {
+            return Err(ZKError::ResponseError);
+        }
 
-        self.response = LittleEndian::read_u16(&recv_buf[0..2]);
-        self.data = recv_buf[8..len].to_vec(); // skip header
+        for (i, field) in self.header.iter_mut().enumerate() {
+            *field = LittleEndian::read_u16(&recv_buf[i * 2..i * 2 + 2]);
+        }
+        self.response = self.header[0];
+        self.data = recv_buf[8..].to_vec(); // skip header
         Ok(CommandResponse {
             status: matches!(
                 self.response,
@@ -304,12 +1224,126 @@ impl ZK {
         })
     }
 
-    pub fn read_sizes(&mut self) -> Result<(), ZKError> {
+    /// Acknowledges a packet pushed by the device, such as a registered event.
+    fn ack_ok(&mut self) -> Result<(), ZKError> {
+        let reply_id = self.reply_id;
+        self.reply_id = 0xffff - 1;
+        let buf = self.create_header(consts::CMD_ACK_OK, &[]);
+        self.reply_id = reply_id;
+        let buf = buf?;
+
+        self.socket
+            .send_to(&buf, self.address)
+            .map_err(|_e| ZKError::NetworkError)?;
+        Ok(())
+    }
+
+    pub fn get_firmware_version(&mut self) -> Result<String, ZKError> {
+        let response = self.send_command(consts::CMD_GET_VERSION, &[], 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        Ok(decode_str(&self.data))
+    }
+
+    pub fn get_serialnumber(&mut self) -> Result<Option<String>, ZKError> {
+        self.get_option("~SerialNumber")
+    }
+
+    pub fn get_platform(&mut self) -> Result<Option<String>, ZKError> {
+        self.get_option("~Platform")
+    }
+
+    pub fn get_mac(&mut self) -> Result<Option<String>, ZKError> {
+        self.get_option("MAC")
+    }
+
+    pub fn get_device_name(&mut self) -> Result<Option<String>, ZKError> {
+        self.get_option("~DeviceName")
+    }
+
+    pub fn get_fp_version(&mut self) -> Result<Option<u32>, ZKError> {
+        self.read_numeric_option("~ZKFPVersion")
+    }
+
+    pub fn get_face_version(&mut self) -> Result<Option<u32>, ZKError> {
+        self.read_numeric_option("ZKFaceVersion")
+    }
+
+    pub fn get_pin_width(&mut self) -> Result<Option<u32>, ZKError> {
+        self.read_numeric_option("~PIN2Width")
+    }
+
+    pub fn get_extend_fmt(&mut self) -> Result<Option<u32>, ZKError> {
+        self.read_numeric_option("~UserExtFmt")
+    }
+
+    /// Collects all identification details of the device in one struct.
+    /// Options the firmware does not know are left as `None`.
+    pub fn get_device_info(&mut self) -> Result<DeviceInfo, ZKError> {
+        Ok(DeviceInfo {
+            firmware_version: self.get_firmware_version()?,
+            serial_number: self.get_serialnumber()?,
+            platform: self.get_platform()?,
+            mac: self.get_mac()?,
+            device_name: self.get_device_name()?,
+            fp_version: self.get_fp_version()?,
+            face_version: self.get_face_version()?,
+            pin_width: self.get_pin_width()?,
+            user_ext_fmt: self.get_extend_fmt()?,
+        })
+    }
+
+    /// Reads a device option (`CMD_OPTIONS_RRQ`). Returns `None` when the
+    /// device does not know the key.
+    pub fn get_option(&mut self, key: &str) -> Result<Option<String>, ZKError> {
+        let mut command_string = key.as_bytes().to_vec();
+        command_string.push(0);
+
+        let response = self.send_command(consts::CMD_OPTIONS_RRQ, &command_string, 1024)?;
+        if !response.status {
+            return Ok(None);
+        }
+
+        Ok(parse_option_reply(&self.data, key))
+    }
+
+    /// Writes a device option (`CMD_OPTIONS_WRQ`) and makes the device reload
+    /// its configuration.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), ZKError> {
+        let mut command_string = format!("{}={}", key, value).into_bytes();
+        command_string.push(0);
+
+        let response = self.send_command(consts::CMD_OPTIONS_WRQ, &command_string, 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.refresh_option()
+    }
+
+    pub fn refresh_option(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_REFRESHOPTION, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    /// Options missing on the device or not holding a number read as `None`.
+    fn read_numeric_option(&mut self, key: &str) -> Result<Option<u32>, ZKError> {
+        Ok(self.get_option(key)?.and_then(|value| value.parse().ok()))
+    }
+
+    pub fn read_sizes(&mut self) -> Result<DeviceSizes, ZKError> {
         let response = self.send_command(consts::CMD_GET_FREE_SIZES, &[], 1024)?;
         if !response.status {
             return Err(ZKError::ResponseError);
         }
 
+        let mut sizes = DeviceSizes::default();
         let data = &self.data;
         if data.len() >= 80 {
             let mut fields = [0i32; 20];
@@ -317,36 +1351,63 @@ impl ZK {
                 fields[i] = LittleEndian::read_i32(&data[i * 4..(i + 1) * 4]);
             }
 
-            self.users = fields[4] as usize;
-            self.records = fields[8] as usize;
+            sizes.users = fields[4] as usize;
+            sizes.fingers = fields[6] as usize;
+            sizes.records = fields[8] as usize;
+            sizes.dummy = fields[10] as usize;
+            sizes.cards = fields[12] as usize;
+            sizes.fingers_cap = fields[14] as usize;
+            sizes.users_cap = fields[15] as usize;
+            sizes.records_cap = fields[16] as usize;
+            sizes.fingers_av = fields[17] as usize;
+            sizes.users_av = fields[18] as usize;
+            sizes.records_av = fields[19] as usize;
         }
 
-        Ok(())
+        // Face capable devices append three more fields
+        if data.len() >= 92 {
+            sizes.faces = LittleEndian::read_i32(&data[80..84]) as usize;
+            sizes.faces_cap = LittleEndian::read_i32(&data[88..92]) as usize;
+        }
+
+        self.users = sizes.users;
+        self.fingers = sizes.fingers;
+        self.records = sizes.records;
+
+        Ok(sizes)
     }
 
+    /// Lists the users stored on the device. 72-byte records whose user_id is
+    /// not numeric are left out and recorded in [`ZK::skipped_users`].
     pub fn get_users(&mut self) -> Result<Vec<User>, ZKError> {
-        self.read_sizes()?;
-        if self.users == 0 {
+        let mut zk = self.disabled()?;
+        zk.skipped_users.clear();
+        zk.read_sizes()?;
+        if zk.users == 0 {
             return Ok(vec![]);
         }
 
         let (mut data, size) =
-            self.read_with_buffer(consts::CMD_USERTEMP_RRQ, consts::FCT_USER, 0)?;
+            zk.read_with_buffer(consts::CMD_USERTEMP_RRQ, consts::FCT_USER, 0)?;
         if size <= 4 {
             return Ok(vec![]);
         }
 
         let total_size = LittleEndian::read_u32(&data[..4]) as usize;
-        self.user_packet_size = total_size / self.users;
+        zk.user_packet_size = total_size / zk.users;
+        if !matches!(zk.user_packet_size, 28 | 72) {
+            return Err(ZKError::UnsupportedRecordSize);
+        }
+        zk.user_packet_size_detected = true;
 
         let mut users = Vec::new();
         data = data[4..].to_vec();
         let mut i = 0;
 
-        while i + self.user_packet_size <= data.len() {
-            let chunk = &data[i..i + self.user_packet_size];
+        while i + zk.user_packet_size <= data.len() {
+            let chunk = &data[i..i + zk.user_packet_size];
 
-            if self.user_packet_size == 28 {
+            if zk.user_packet_size == 28 {
                 let uid = LittleEndian::read_u16(&chunk[0..2]);
                 let privilege: u16 = chunk[2].into();
                 let password = std::str::from_utf8(&chunk[3..8])
@@ -358,22 +1419,316 @@ impl ZK {
                     .trim_end_matches('\0')
                     .to_string();
                 let card: u64 = LittleEndian::read_u32(&chunk[16..20]).into();
-                let group_id = chunk[20].to_string();
+                let group_id = chunk[21].to_string();
                 let user_id = LittleEndian::read_u32(&chunk[24..28]);
 
                 users.push(User::new(
                     uid, name, privilege, password, group_id, user_id, card,
                 ));
+            } else {
+                let uid = LittleEndian::read_u16(&chunk[0..2]);
+                let privilege: u16 = chunk[2].into();
+                let password = decode_str(&chunk[3..11]);
+                let name = decode_str(&chunk[11..35]);
+                let card: u64 = LittleEndian::read_u32(&chunk[35..39]).into();
+                let group_id = decode_str(&chunk[40..47]);
+                let raw_user_id = decode_str(&chunk[48..72]);
+
+                match raw_user_id.parse::<u32>() {
+                    Ok(user_id) => users.push(User::new(
+                        uid, name, privilege, password, group_id, user_id, card,
+                    )),
+                    Err(_) => {
+                        if zk.verbose {
+                            eprintln!("skipping uid {uid}: user id {raw_user_id:?} is not numeric");
+                        }
+                        zk.skipped_users.push((uid, raw_user_id));
+                    }
+                }
             }
 
-            // Optionally handle 72-byte format here
-
-            i += self.user_packet_size;
+            i += zk.user_packet_size;
         }
 
         Ok(users)
     }
 
+    /// Writes a user record (`CMD_USER_WRQ`) in the packing matching the
+    /// device's `user_packet_size`. A `uid` of 0 picks the next free uid, and a
+    /// `user_id` of 0 reuses the uid. Returns the uid that was written.
+    pub fn set_user(&mut self, user: &User) -> Result<u16, ZKError> {
+        let mut user = user.clone();
+        if user.uid == 0 {
+            let users = self.get_users()?;
+            user.uid = users
+                .iter()
+                .map(|u| u.uid)
+                .max()
+                .unwrap_or(0)
+                .checked_add(1)
+                .ok_or(ZKError::NoFreeUid)?;
+        } else {
+            self.detect_user_packet_size()?;
+        }
+        if user.user_id == 0 {
+            user.user_id = user.uid.into();
+        }
+
+        let packed = if self.user_packet_size == 28 {
+            user.repack29()
+        } else {
+            user.repack73()
+        };
+
+        // The leading byte of the repacked record is only used by bulk uploads
+        let response = self.send_command(consts::CMD_USER_WRQ, &packed[1..], 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.refresh_data()?;
+        Ok(user.uid)
+    }
+
+    /// Reads the users once so `user_packet_size` reflects the device. A device
+    /// without users keeps the default 28 byte packing.
+    fn detect_user_packet_size(&mut self) -> Result<(), ZKError> {
+        if !self.user_packet_size_detected {
+            self.get_users()?;
+        }
+        Ok(())
+    }
+
+    /// Same as [`ZK::set_user`], but stores the assigned uid and user_id back
+    /// into `user`.
+    pub fn save_user(&mut self, user: &mut User) -> Result<(), ZKError> {
+        user.uid = self.set_user(user)?;
+        if user.user_id == 0 {
+            user.user_id = user.uid.into();
+        }
+        Ok(())
+    }
+
+    /// Deletes a user (`CMD_DELETE_USER`). When `uid` is 0 the uid is looked
+    /// up from `user_id`.
+    pub fn delete_user(&mut self, uid: u16, user_id: u32) -> Result<(), ZKError> {
+        let uid = if uid == 0 {
+            self.find_uid(user_id)?
+        } else {
+            uid
+        };
+
+        let response = self.send_command(consts::CMD_DELETE_USER, &uid.to_le_bytes(), 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.refresh_data()
+    }
+
+    /// Deletes the template of finger `fid` for `uid`. Over TCP the device
+    /// addresses templates by user_id (`_CMD_DEL_USER_TEMP`), otherwise
+    /// `CMD_DELETE_USERTEMP` is used.
+    pub fn delete_user_template(&mut self, uid: u16, fid: u8) -> Result<(), ZKError> {
+        let response = if matches!(self.socket, ZkSocket::Tcp(_)) {
+            let user_id = self
+                .get_users()?
+                .into_iter()
+                .find(|u| u.uid == uid)
+                .ok_or(ZKError::UserNotFound)?
+                .user_id;
+
+            let mut command_string = [0u8; 25];
+            let user_id = user_id.to_string();
+            let len = user_id.len().min(24);
+            command_string[..len].copy_from_slice(&user_id.as_bytes()[..len]);
+            command_string[24] = fid;
+
+            self.send_command(consts::_CMD_DEL_USER_TEMP, &command_string, 1024)?
+        } else {
+            let mut command_string = uid.to_le_bytes().to_vec();
+            command_string.push(fid);
+
+            self.send_command(consts::CMD_DELETE_USERTEMP, &command_string, 1024)?
+        };
+
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.refresh_data()
+    }
+
+    fn find_uid(&mut self, user_id: u32) -> Result<u16, ZKError> {
+        self.get_users()?
+            .into_iter()
+            .find(|u| u.user_id == user_id)
+            .map(|u| u.uid)
+            .ok_or(ZKError::UserNotFound)
+    }
+
+    /// Deletes all attendance records (`CMD_CLEAR_ATTLOG`).
+    pub fn clear_attendance(&mut self) -> Result<ClearReport, ZKError> {
+        self.clear(consts::CMD_CLEAR_ATTLOG)
+    }
+
+    /// Deletes all users, templates and records (`CMD_CLEAR_DATA`).
+    pub fn clear_data(&mut self) -> Result<ClearReport, ZKError> {
+        self.clear(consts::CMD_CLEAR_DATA)
+    }
+
+    /// Deletes the operation log (`CMD_CLEAR_OPLOG`).
+    pub fn clear_oplog(&mut self) -> Result<ClearReport, ZKError> {
+        self.clear(consts::CMD_CLEAR_OPLOG)
+    }
+
+    /// Drops the admin privilege of every user (`CMD_CLEAR_ADMIN`).
+    pub fn clear_admins(&mut self) -> Result<ClearReport, ZKError> {
+        self.clear(consts::CMD_CLEAR_ADMIN)
+    }
+
+    /// Restores the access control settings to their defaults (`CMD_CLEAR_ACC`).
+    pub fn reset_access_control(&mut self) -> Result<ClearReport, ZKError> {
+        self.clear(consts::CMD_CLEAR_ACC)
+    }
+
+    fn clear(&mut self, command: u16) -> Result<ClearReport, ZKError> {
+        let before = self.read_sizes()?;
+
+        let response = self.send_command(command, &[], 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        let after = self.read_sizes()?;
+        Ok(ClearReport { before, after })
+    }
+
+    pub fn refresh_data(&mut self) -> Result<(), ZKError> {
+        let response = self.send_command(consts::CMD_REFRESHDATA, &[], 8)?;
+        if response.status {
+            Ok(())
+        } else {
+            Err(ZKError::ResponseError)
+        }
+    }
+
+    /// Downloads every fingerprint template stored on the device.
+    pub fn get_templates(&mut self) -> Result<Vec<Finger>, ZKError> {
+        let mut zk = self.disabled()?;
+        zk.read_sizes()?;
+        if zk.fingers == 0 {
+            return Ok(vec![]);
+        }
+
+        let (data, _) = zk.read_with_buffer(consts::CMD_USERTEMP_RRQ, consts::FCT_FINGERTMP, 0)?;
+
+        Ok(decode_template_records(&data))
+    }
+
+    /// Downloads the template of finger `fid` for `uid` (`_CMD_GET_USERTEMP`).
+    /// Returns `None` when that finger is not enrolled.
+    pub fn get_user_template(&mut self, uid: u16, fid: u8) -> Result<Option<Finger>, ZKError> {
+        let mut zk = self.disabled()?;
+        let mut command_string = uid.to_le_bytes().to_vec();
+        command_string.push(fid);
+
+        zk.send_command(consts::_CMD_GET_USERTEMP, &command_string, 1024 + 8)?;
+        if !matches!(zk.response, consts::CMD_DATA | consts::CMD_PREPARE_DATA) {
+            return Ok(None);
+        }
+
+        let mut template = zk.receive_chunk()?;
+        // The device appends a padding byte, and sometimes six more zeros
+        template.pop();
+        if template.ends_with(&[0; 6]) {
+            template.truncate(template.len() - 6);
+        }
+        if template.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Finger::new(uid, fid, 1, template)))
+    }
+
+    /// Uploads a user together with all of its fingerprint templates in one go
+    /// (`_CMD_SAVE_USERTEMPS`).
+    pub fn save_user_templates(&mut self, user: &User, fingers: &[Finger]) -> Result<(), ZKError> {
+        self.detect_user_packet_size()?;
+        let upack = if self.user_packet_size == 28 {
+            user.repack29()
+        } else {
+            user.repack73()
+        };
+
+        let mut table = Vec::new();
+        let mut fpack = Vec::new();
+        for finger in fingers {
+            let template = finger.repack_only();
+            table.push(2);
+            table.extend(&user.uid.to_le_bytes());
+            table.push(0x10 + finger.fid);
+            table.extend(&(fpack.len() as u32).to_le_bytes());
+            fpack.extend(template);
+        }
+
+        let mut packet = Vec::with_capacity(12 + upack.len() + table.len() + fpack.len());
+        packet.extend(&(upack.len() as u32).to_le_bytes());
+        packet.extend(&(table.len() as u32).to_le_bytes());
+        packet.extend(&(fpack.len() as u32).to_le_bytes());
+        packet.extend(upack);
+        packet.extend(table);
+        packet.extend(fpack);
+        self.send_with_buffer(&packet)?;
+
+        let mut command_string = Vec::with_capacity(8);
+        command_string.extend(&12u32.to_le_bytes());
+        command_string.extend(&0u16.to_le_bytes());
+        command_string.extend(&8u16.to_le_bytes());
+        let response = self.send_command(consts::_CMD_SAVE_USERTEMPS, &command_string, 1024)?;
+        if !response.status {
+            return Err(ZKError::ResponseError);
+        }
+
+        self.refresh_data()
+    }
+
+    /// Uploads a payload of any size into the device's data buffer: announces
+    /// the size with `CMD_PREPARE_DATA`, then streams it with `CMD_DATA` in
+    /// chunks the device accepts. On failure the buffer is released with
+    /// `CMD_FREE_DATA`.
+    pub fn send_with_buffer(&mut self, buffer: &[u8]) -> Result<(), ZKError> {
+        self.free_data()?;
+
+        let result = self.send_buffer_chunks(buffer);
+        if result.is_err() {
+            let _ = self.free_data();
+        }
+        result
+    }
+
+    fn send_buffer_chunks(&mut self, buffer: &[u8]) -> Result<(), ZKError> {
+        const MAX_CHUNK: usize = 1024;
+
+        self.send_command(
+            consts::CMD_PREPARE_DATA,
+            &(buffer.len() as u32).to_le_bytes(),
+            1024,
+        )?;
+        if self.response != consts::CMD_ACK_OK {
+            return Err(ZKError::ResponseError);
+        }
+
+        for chunk in buffer.chunks(MAX_CHUNK) {
+            self.send_command(consts::CMD_DATA, chunk, 1024)?;
+            if self.response != consts::CMD_ACK_OK {
+                return Err(ZKError::ResponseError);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn free_data(&mut self) -> Result<(), ZKError> {
         let response = self.send_command(consts::CMD_FREE_DATA, &[], 8)?;
         if response.status {
@@ -395,19 +1750,22 @@ impl ZK {
                 let mut remaining = size;
 
                 while remaining > 0 {
-                    let mut buffer = vec![0u8; 1032];
-                    let len = self.socket.recv(&mut buffer).unwrap();
-                    let header = LittleEndian::read_u16(&buffer[..2]);
-                    if header != consts::CMD_DATA {
+                    let buffer = self
+                        .socket
+                        .recv_packet(1032)
+                        .map_err(|_e| ZKError::NetworkError)?;
+                    if buffer.len() < 8 || LittleEndian::read_u16(&buffer[..2]) != consts::CMD_DATA
+                    {
                         break;
                     }
-                    data.extend_from_slice(&buffer[8..len]);
-                    remaining -= len - 8;
+                    data.extend_from_slice(&buffer[8..]);
+                    remaining = remaining.saturating_sub(buffer.len() - 8);
                 }
 
                 // Read ACK_OK to complete transfer
-                let mut ack_buf = [0u8; 16];
-                let _ = self.socket.recv(&mut ack_buf).unwrap();
+                self.socket
+                    .recv_packet(16)
+                    .map_err(|_e| ZKError::NetworkError)?;
 
                 Ok(data)
             }
@@ -429,8 +1787,6 @@ impl ZK {
         fct: u32,
         ext: u32,
     ) -> Result<(Vec<u8>, usize), ZKError> {
-        const MAX_CHUNK: usize = 16 * 1024;
-
         let mut buf = Vec::new();
         let mut command_string = Vec::with_capacity(11);
         command_string.push(1); // 1 byte
@@ -438,9 +1794,7 @@ impl ZK {
         command_string.extend(&fct.to_le_bytes());
         command_string.extend(&ext.to_le_bytes());
 
-        let response = self
-            .send_command(consts::CMD_PREPARE_BUFFER, &command_string, 1024)
-            .unwrap();
+        let response = self.send_command(consts::CMD_PREPARE_BUFFER, &command_string, 1024)?;
 
         if !response.status {
             return Err(ZKError::ResponseError);
@@ -450,15 +1804,18 @@ impl ZK {
             return Ok((self.data.clone(), self.data.len()));
         }
 
+        if self.data.len() < 5 {
+            return Err(ZKError::ResponseError);
+        }
         let total_size = LittleEndian::read_u32(&self.data[1..5]) as usize;
-        let remain = total_size % MAX_CHUNK;
-        let packets = total_size / MAX_CHUNK;
+        let remain = total_size % READ_CHUNK;
+        let packets = total_size / READ_CHUNK;
         let mut start = 0;
 
         for _ in 0..packets {
-            let chunk = self.read_chunk(start, MAX_CHUNK)?;
+            let chunk = self.read_chunk(start, READ_CHUNK)?;
             buf.extend(chunk);
-            start += MAX_CHUNK;
+            start += READ_CHUNK;
         }
 
         if remain > 0 {
@@ -479,3 +1836,400 @@ impl ZK {
         self.receive_chunk()
     }
 }
+
+impl Drop for ZK {
+    fn drop(&mut self) {
+        if self.is_connect {
+            let _ = self.disconnect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attandance::{Punch, VerifyMode};
+    use crate::oplog::Operation;
+    use std::net::TcpListener;
+
+    fn tcp_frame(packet: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend(&consts::MACHINE_PREPARE_DATA_1.to_le_bytes());
+        frame.extend(&consts::MACHINE_PREPARE_DATA_2.to_le_bytes());
+        frame.extend(&(packet.len() as u32).to_le_bytes());
+        frame.extend(packet);
+        frame
+    }
+
+    #[test]
+    fn recv_packet_handles_split_and_coalesced_tcp_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let first: Vec<u8> = (0u8..12).collect();
+        let second: Vec<u8> = (100u8..110).collect();
+        let third: Vec<u8> = (200u8..208).collect();
+        let (f1, f2, f3) = (tcp_frame(&first), tcp_frame(&second), tcp_frame(&third));
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // First frame split inside the envelope and inside the body
+            for part in [&f1[..3], &f1[3..10], &f1[10..]] {
+                stream.write_all(part).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            // Second and third frames coalesced in a single write
+            stream.write_all(&[f2, f3].concat()).unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut socket = ZkSocket::Tcp(stream);
+
+        assert_eq!(socket.recv_packet(1024).unwrap(), first);
+        assert_eq!(socket.recv_packet(1024).unwrap(), second);
+        assert_eq!(socket.recv_packet(1024).unwrap(), third);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn recv_packet_keeps_a_frame_that_spans_a_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let packet: Vec<u8> = (0u8..16).collect();
+        let frame = tcp_frame(&packet);
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Nothing at all for longer than the read timeout
+            std::thread::sleep(Duration::from_millis(150));
+            // Then half a frame, stalling past the read timeout before the rest
+            stream.write_all(&frame[..5]).unwrap();
+            std::thread::sleep(Duration::from_millis(150));
+            stream.write_all(&frame[5..]).unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let mut socket = ZkSocket::Tcp(stream);
+
+        let err = socket.recv_packet(1024).unwrap_err();
+        assert!(is_timeout(&err));
+        assert_eq!(socket.recv_packet(1024).unwrap(), packet);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn recv_packet_rejects_oversized_tcp_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut top = Vec::new();
+            top.extend(&consts::MACHINE_PREPARE_DATA_1.to_le_bytes());
+            top.extend(&consts::MACHINE_PREPARE_DATA_2.to_le_bytes());
+            top.extend(&u32::MAX.to_le_bytes());
+            stream.write_all(&top).unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut socket = ZkSocket::Tcp(stream);
+
+        let err = socket.recv_packet(1024).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn encode_time_round_trips_through_decode_time() {
+        for (y, m, d, h, mi, sec) in [
+            (2024, 2, 29, 23, 59, 58),
+            (2000, 1, 1, 0, 0, 0),
+            (2099, 12, 31, 23, 59, 59),
+        ] {
+            let t = NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_hms_opt(h, mi, sec)
+                .unwrap();
+
+            let packed = encode_time(&t).unwrap();
+            assert_eq!(decode_time(&packed.to_le_bytes()).unwrap(), t);
+        }
+
+        for year in [1999, 2100] {
+            let t = NaiveDate::from_ymd_opt(year, 6, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap();
+
+            assert!(matches!(encode_time(&t), Err(ZKError::InvalidTime)));
+        }
+    }
+
+    #[test]
+    fn decode_time_rejects_invalid_dates() {
+        // 2023-02-31 00:00:00
+        let packed = ((23 * 12 + 1) * 31 + 30) * 24 * 60 * 60u32;
+
+        assert!(matches!(
+            decode_time(&packed.to_le_bytes()),
+            Err(ZKError::InvalidTime)
+        ));
+    }
+
+    #[test]
+    fn parse_option_reply_requires_matching_key() {
+        assert_eq!(
+            parse_option_reply(b"~Platform=ZMM220_TFT\0\0\0", "~Platform"),
+            Some("ZMM220_TFT".to_string())
+        );
+        assert_eq!(
+            parse_option_reply(b"Name= padded \0", "Name"),
+            Some(" padded ".to_string())
+        );
+        assert_eq!(
+            parse_option_reply(b"VOLUME=\0", "VOLUME"),
+            Some(String::new())
+        );
+        assert_eq!(parse_option_reply(b"~Platform\0", "~Platform"), None);
+        assert_eq!(parse_option_reply(b"MAC=00:17:61\0", "~Platform"), None);
+    }
+
+    fn sample_time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 17)
+            .unwrap()
+            .and_hms_opt(8, 30, 15)
+            .unwrap()
+    }
+
+    fn with_total_size(records: &[u8]) -> Vec<u8> {
+        let mut data = (records.len() as u32).to_le_bytes().to_vec();
+        data.extend(records);
+        data
+    }
+
+    fn sample_users() -> Vec<User> {
+        vec![User::new(
+            3,
+            "Ana".to_string(),
+            0,
+            String::new(),
+            String::new(),
+            1001,
+            0,
+        )]
+    }
+
+    #[test]
+    fn decode_attendance_records_reads_8_byte_layout() {
+        let time = encode_time(&sample_time()).unwrap().to_le_bytes();
+        // uid, status, time, punch
+        let mut records = vec![3, 0, 1];
+        records.extend(time);
+        records.push(0);
+        records.extend([7, 0, 2]);
+        records.extend(time);
+        records.push(1);
+
+        let att =
+            decode_attendance_records(&with_total_size(&records), 2, &sample_users()).unwrap();
+
+        assert_eq!(att.len(), 2);
+        assert_eq!((att[0].uid, att[0].user_id), (3, 1001));
+        assert_eq!(att[0].timestamp, sample_time());
+        assert_eq!(att[0].status, VerifyMode::Fingerprint);
+        assert_eq!(att[0].punch, Punch::CheckIn);
+        // No user with uid 7: the uid doubles as user_id
+        assert_eq!((att[1].uid, att[1].user_id), (7, 7));
+        assert_eq!(att[1].status, VerifyMode::Card);
+        assert_eq!(att[1].punch, Punch::CheckOut);
+    }
+
+    #[test]
+    fn decode_attendance_records_reads_16_byte_layout() {
+        let time = encode_time(&sample_time()).unwrap().to_le_bytes();
+        let mut records = Vec::new();
+        // user_id, time, status, punch, reserved, workcode
+        for (id, workcode) in [(1001u32, 5u32), (3, 0), (42, 0)] {
+            records.extend(id.to_le_bytes());
+            records.extend(time);
+            records.extend([15, 4, 0, 0]);
+            records.extend(workcode.to_le_bytes());
+        }
+
+        let att =
+            decode_attendance_records(&with_total_size(&records), 3, &sample_users()).unwrap();
+
+        assert_eq!(att.len(), 3);
+        assert_eq!((att[0].uid, att[0].user_id, att[0].workcode), (3, 1001, 5));
+        assert_eq!(att[0].status, VerifyMode::Face);
+        assert_eq!(att[0].punch, Punch::OvertimeIn);
+        // Older firmwares store the uid in the user_id field
+        assert_eq!((att[1].uid, att[1].user_id), (3, 1001));
+        // No user matches either way
+        assert_eq!((att[2].uid, att[2].user_id), (42, 42));
+    }
+
+    #[test]
+    fn decode_attendance_records_reads_40_byte_layout() {
+        let time = encode_time(&sample_time()).unwrap().to_le_bytes();
+        let mut records = Vec::new();
+        // uid, user_id (24), status, time, punch, reserved (8)
+        for (uid, user_id) in [(3u16, &b"1001"[..]), (3, b"abc"), (9, b"")] {
+            records.extend(uid.to_le_bytes());
+            let mut field = [0u8; 24];
+            field[..user_id.len()].copy_from_slice(user_id);
+            records.extend(field);
+            records.push(0);
+            records.extend(time);
+            records.push(2);
+            records.extend([0u8; 8]);
+        }
+
+        let att =
+            decode_attendance_records(&with_total_size(&records), 3, &sample_users()).unwrap();
+
+        assert_eq!(att.len(), 3);
+        assert_eq!((att[0].uid, att[0].user_id), (3, 1001));
+        assert_eq!(att[0].status, VerifyMode::Password);
+        assert_eq!(att[0].punch, Punch::BreakOut);
+        assert_eq!(att[0].timestamp, sample_time());
+        // A non-numeric user_id falls back to the user list
+        assert_eq!((att[1].uid, att[1].user_id), (3, 1001));
+        // ... and to the uid when no user matches
+        assert_eq!((att[2].uid, att[2].user_id), (9, 9));
+    }
+
+    #[test]
+    fn decode_attendance_records_handles_truncated_input() {
+        let time = encode_time(&sample_time()).unwrap().to_le_bytes();
+        let mut records = vec![3, 0, 1];
+        records.extend(time);
+        records.push(0);
+
+        // The declared size covers two records but only one and a half arrived
+        let mut data = 16u32.to_le_bytes().to_vec();
+        data.extend(&records);
+        data.extend(&records[..4]);
+        let att = decode_attendance_records(&data, 2, &sample_users()).unwrap();
+        assert_eq!(att.len(), 1);
+        assert_eq!(att[0].user_id, 1001);
+
+        assert!(decode_attendance_records(&data[..3], 2, &[])
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            decode_attendance_records(&with_total_size(&records[..6]), 1, &[]),
+            Err(ZKError::UnsupportedRecordSize)
+        ));
+    }
+
+    #[test]
+    fn decode_template_records_reads_variable_length_records() {
+        let first = Finger::new(3, 0, 1, vec![0xaa; 10]);
+        let second = Finger::new(7, 6, 3, vec![1, 2, 3]);
+        let records = [first.repack(), second.repack()].concat();
+
+        let fingers = decode_template_records(&with_total_size(&records));
+
+        assert_eq!(fingers.len(), 2);
+        assert_eq!(fingers[0], first);
+        assert_eq!(
+            (fingers[1].uid, fingers[1].fid, fingers[1].valid),
+            (7, 6, 3)
+        );
+        assert_eq!(fingers[1].template, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn decode_template_records_handles_truncated_input() {
+        let first = Finger::new(3, 0, 1, vec![0xaa; 10]).repack();
+        let second = Finger::new(7, 6, 3, vec![1, 2, 3]).repack();
+
+        // Declared size covers both, but the second record is cut short
+        let mut data = ((first.len() + second.len()) as u32).to_le_bytes().to_vec();
+        data.extend(&first);
+        data.extend(&second[..5]);
+        let fingers = decode_template_records(&data);
+        assert_eq!(fingers.len(), 1);
+        assert_eq!(fingers[0].uid, 3);
+
+        // A record whose size field runs past the data ends decoding
+        let mut broken = first.clone();
+        broken[0] = 0xff;
+        assert!(decode_template_records(&with_total_size(&broken)).is_empty());
+
+        assert!(decode_template_records(&[1, 0]).is_empty());
+    }
+
+    fn oplog_record(admin_id: u16, operation: u8, params: [u16; 4]) -> Vec<u8> {
+        let mut record = admin_id.to_le_bytes().to_vec();
+        record.extend([operation, 0]);
+        record.extend(encode_time(&sample_time()).unwrap().to_le_bytes());
+        for param in params {
+            record.extend(param.to_le_bytes());
+        }
+        record
+    }
+
+    #[test]
+    fn decode_oplog_records_reads_16_byte_layout() {
+        let records = [
+            oplog_record(1, 0, [0; 4]),
+            oplog_record(0, 6, [3, 1001, 2, 0]),
+        ]
+        .concat();
+
+        let entries = decode_oplog_records(&with_total_size(&records)).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].admin_id, 1);
+        assert_eq!(entries[0].operation, Operation::from(0));
+        assert_eq!(entries[0].timestamp, sample_time());
+        assert_eq!(entries[1].admin_id, 0);
+        assert_eq!(entries[1].operation, Operation::from(6));
+        assert_eq!(entries[1].params, [3, 1001, 2, 0]);
+    }
+
+    #[test]
+    fn decode_oplog_records_handles_truncated_input() {
+        let record = oplog_record(1, 0, [0; 4]);
+
+        // Declared size covers two records but only one arrived
+        let mut data = 32u32.to_le_bytes().to_vec();
+        data.extend(&record);
+        assert!(matches!(
+            decode_oplog_records(&data),
+            Err(ZKError::ResponseError)
+        ));
+
+        assert!(matches!(
+            decode_oplog_records(&with_total_size(&record[..12])),
+            Err(ZKError::UnsupportedRecordSize)
+        ));
+        assert!(decode_oplog_records(&[0, 0]).unwrap().is_empty());
+        assert!(decode_oplog_records(&with_total_size(&[]))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn make_commkey_matches_reference() {
+        // Values produced by pyzk's make_commkey
+        assert_eq!(make_commkey(123456, 0x2b5c, 50), [38, 127, 50, 210]);
+        assert_eq!(make_commkey(0, 1, 50), [97, 125, 50, 121]);
+    }
+}
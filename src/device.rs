@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+/// Storage counters and capacities reported by `CMD_GET_FREE_SIZES`.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSizes {
+    pub users: usize,
+    pub fingers: usize,
+    pub records: usize,
+    pub dummy: usize,
+    pub cards: usize,
+    pub fingers_cap: usize,
+    pub users_cap: usize,
+    pub records_cap: usize,
+    pub fingers_av: usize,
+    pub users_av: usize,
+    pub records_av: usize,
+    pub faces: usize,
+    pub faces_cap: usize,
+}
+
+/// Identification details of a terminal, see `ZK::get_device_info`.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub firmware_version: String,
+    pub serial_number: Option<String>,
+    pub platform: Option<String>,
+    pub mac: Option<String>,
+    pub device_name: Option<String>,
+    pub fp_version: Option<u32>,
+    pub face_version: Option<u32>,
+    pub pin_width: Option<u32>,
+    pub user_ext_fmt: Option<u32>,
+}
+
+/// Door sensor state reported by `CMD_DOORSTATE_RRQ`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorState {
+    Open,
+    Closed,
+}
+
+/// Standard voice prompts played by `CMD_TESTVOICE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoicePrompt {
+    ThankYou,
+    IncorrectPassword,
+    AccessDenied,
+    InvalidId,
+    PleaseTryAgain,
+    DuplicateId,
+    ClockIsFull,
+    DuplicateFinger,
+    DuplicatedPunch,
+    BeepKuko,
+    BeepSiren,
+    BeepBell,
+    FingerprintNotEnrolled,
+    PasswordNotEnrolled,
+    BadgeNotEnrolled,
+    FaceNotEnrolled,
+    BeepStandard,
+    InvalidUser,
+    InvalidTimePeriod,
+    InvalidCombination,
+    IllegalAccess,
+    DiskSpaceFull,
+    FingerprintNotRegistered,
+    /// Any other prompt index supported by the firmware.
+    Other(u32),
+}
+
+impl VoicePrompt {
+    pub fn index(&self) -> u32 {
+        match self {
+            VoicePrompt::ThankYou => 0,
+            VoicePrompt::IncorrectPassword => 1,
+            VoicePrompt::AccessDenied => 2,
+            VoicePrompt::InvalidId => 3,
+            VoicePrompt::PleaseTryAgain => 4,
+            VoicePrompt::DuplicateId => 5,
+            VoicePrompt::ClockIsFull => 7,
+            VoicePrompt::DuplicateFinger => 8,
+            VoicePrompt::DuplicatedPunch => 9,
+            VoicePrompt::BeepKuko => 10,
+            VoicePrompt::BeepSiren => 11,
+            VoicePrompt::BeepBell => 13,
+            VoicePrompt::FingerprintNotEnrolled => 20,
+            VoicePrompt::PasswordNotEnrolled => 21,
+            VoicePrompt::BadgeNotEnrolled => 22,
+            VoicePrompt::FaceNotEnrolled => 23,
+            VoicePrompt::BeepStandard => 24,
+            VoicePrompt::InvalidUser => 30,
+            VoicePrompt::InvalidTimePeriod => 31,
+            VoicePrompt::InvalidCombination => 32,
+            VoicePrompt::IllegalAccess => 33,
+            VoicePrompt::DiskSpaceFull => 34,
+            VoicePrompt::FingerprintNotRegistered => 36,
+            VoicePrompt::Other(index) => *index,
+        }
+    }
+}
+
+/// Device counters before and after a destructive clear command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClearReport {
+    pub before: DeviceSizes,
+    pub after: DeviceSizes,
+}
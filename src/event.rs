@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::attandance::Attendance;
+
+/// An event pushed by the device after registering with `CMD_REG_EVENT`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LiveEvent {
+    /// A user punched in or out (`EF_ATTLOG`).
+    Attendance(Attendance),
+    /// A finger was placed on the sensor (`EF_FINGER`).
+    Finger,
+    /// A new user was enrolled at the terminal (`EF_ENROLLUSER`).
+    EnrollUser { uid: u16 },
+    /// Progress or result of a fingerprint enrollment (`EF_ENROLLFINGER`).
+    EnrollFinger { result: u16, size: u16 },
+    /// A key was pressed on the keypad (`EF_BUTTON`).
+    Button { key: u16 },
+    /// The door lock was released (`EF_UNLOCK`).
+    Unlock,
+    /// A verification was attempted (`EF_VERIFY`).
+    Verify { uid: u16 },
+    /// An alarm was raised (`EF_ALARM`).
+    Alarm { code: u16 },
+    /// Any event this crate does not decode, including attendance records
+    /// whose user id is not numeric.
+    Unknown { event: u16, data: Vec<u8> },
+    /// No event arrived within the socket read timeout.
+    Heartbeat,
+}
+
+/// Progress of [`crate::base::ZK::enroll_user`]. An enrollment takes three
+/// presses of the same finger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnrollStep {
+    /// Waiting for press `n` of 3.
+    Place(u8),
+    /// Still waiting on the device during press `n` of 3.
+    Waiting(u8),
+    /// Press `n` of 3 was read.
+    Captured(u8),
+}
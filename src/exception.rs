@@ -11,6 +11,33 @@ pub enum ZKError {
     #[error("Network error")]
     NetworkError,
 
+    #[error("Unauthorized, wrong comm key")]
+    Unauthorized,
+
+    #[error("User id {0:?} is not numeric")]
+    InvalidUserId(String),
+
+    #[error("No free uid left")]
+    NoFreeUid,
+
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Invalid device time")]
+    InvalidTime,
+
+    #[error("Command not supported by the device")]
+    Unsupported,
+
+    #[error("Timed out waiting for the device")]
+    Timeout,
+
+    #[error("Enrollment failed")]
+    EnrollFailed,
+
+    #[error("Cancelled")]
+    Cancelled,
+
     #[error("Unsupported Record Size")]
     UnsupportedRecordSize,
 }
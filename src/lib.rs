@@ -1,6 +1,9 @@
 pub mod attandance;
 pub mod base;
-mod consts;
-mod exception;
-mod finger;
+pub mod consts;
+pub mod device;
+pub mod event;
+pub mod exception;
+pub mod finger;
+pub mod oplog;
 pub mod user;
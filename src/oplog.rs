@@ -0,0 +1,100 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What an administrator did at the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Operation {
+    PowerOn,
+    PowerOff,
+    VerifyFailed,
+    Alarm,
+    EnterMenu,
+    ChangeSettings,
+    EnrollFingerprint,
+    EnrollPassword,
+    EnrollCard,
+    DeleteUser,
+    DeleteFingerprint,
+    DeletePassword,
+    DeleteCard,
+    ClearData,
+    SetTime,
+    ClearAttendance,
+    ClearAdmins,
+    Unlock,
+    EnrollUser,
+    DuressAlarm,
+    Unknown(u8),
+}
+
+impl From<u8> for Operation {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Operation::PowerOn,
+            1 => Operation::PowerOff,
+            2 => Operation::VerifyFailed,
+            3 => Operation::Alarm,
+            4 => Operation::EnterMenu,
+            5 => Operation::ChangeSettings,
+            6 => Operation::EnrollFingerprint,
+            7 => Operation::EnrollPassword,
+            8 => Operation::EnrollCard,
+            9 => Operation::DeleteUser,
+            10 => Operation::DeleteFingerprint,
+            11 => Operation::DeletePassword,
+            12 => Operation::DeleteCard,
+            13 => Operation::ClearData,
+            21 => Operation::SetTime,
+            23 => Operation::ClearAttendance,
+            24 => Operation::ClearAdmins,
+            29 => Operation::Unlock,
+            30 => Operation::EnrollUser,
+            32 => Operation::DuressAlarm,
+            other => Operation::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OpLogEntry {
+    pub admin_id: u16,
+    pub operation: Operation,
+    /// Device-local time of the operation.
+    pub timestamp: NaiveDateTime,
+    /// Operation specific parameters, e.g. the affected user.
+    pub params: [u16; 4],
+}
+
+impl OpLogEntry {
+    pub fn new(
+        admin_id: u16,
+        operation: Operation,
+        timestamp: NaiveDateTime,
+        params: [u16; 4],
+    ) -> Self {
+        Self {
+            admin_id,
+            operation,
+            timestamp,
+            params,
+        }
+    }
+}
+
+impl fmt::Display for OpLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<OpLog>: {} : {} ({:?}, {:?})",
+            self.admin_id, self.timestamp, self.operation, self.params
+        )
+    }
+}
+
+impl fmt::Debug for OpLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Reuse Display implementation
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -52,7 +52,7 @@ impl User {
         let mut buf = Vec::new();
         buf.write_u8(2).unwrap();
         buf.write_u16::<LittleEndian>(self.uid).unwrap();
-        buf.write_u16::<LittleEndian>(self.privilege).unwrap();
+        buf.write_u8(self.privilege as u8).unwrap();
 
         let mut pw_bytes = [0u8; 5];
         let pw_encoded = self.password.as_bytes();
@@ -66,13 +66,15 @@ impl User {
         name_bytes[..len_name].copy_from_slice(&name_encoded[..len_name]);
         buf.extend_from_slice(&name_bytes);
 
-        buf.write_u64::<LittleEndian>(self.card).unwrap();
-
-        let group_id_num = self.group_id.parse::<u32>().unwrap_or(0);
-        buf.write_u32::<LittleEndian>(group_id_num).unwrap();
+        buf.write_u32::<LittleEndian>(self.card as u32).unwrap();
 
         buf.write_u8(0).unwrap(); // unknown zero
 
+        let group_id_num = self.group_id.parse::<u8>().unwrap_or(0);
+        buf.write_u8(group_id_num).unwrap();
+
+        buf.write_i16::<LittleEndian>(0).unwrap(); // timezone
+
         buf.write_u32::<LittleEndian>(self.user_id).unwrap();
 
         buf
@@ -83,14 +85,12 @@ impl User {
         let mut buf = Vec::new();
         buf.write_u8(2).unwrap();
         buf.write_u16::<LittleEndian>(self.uid).unwrap();
-        buf.write_u16::<LittleEndian>(self.privilege).unwrap();
+        buf.write_u8(self.privilege as u8).unwrap();
 
         let mut pw_bytes = [0u8; 8];
         let pw_encoded = self.password.as_bytes();
-        let len_pw = pw_encoded.len().min(6);
+        let len_pw = pw_encoded.len().min(8);
         pw_bytes[..len_pw].copy_from_slice(&pw_encoded[..len_pw]);
-        pw_bytes[len_pw] = 0;
-        pw_bytes[len_pw + 1] = 0x77;
         buf.extend_from_slice(&pw_bytes);
 
         let mut name_bytes = [0u8; 24];
@@ -99,7 +99,7 @@ impl User {
         name_bytes[..len_name].copy_from_slice(&name_encoded[..len_name]);
         buf.extend_from_slice(&name_bytes);
 
-        buf.write_u64::<LittleEndian>(self.card).unwrap();
+        buf.write_u32::<LittleEndian>(self.card as u32).unwrap();
 
         buf.write_u8(1).unwrap(); // unknown 1
 